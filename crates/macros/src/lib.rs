@@ -3,12 +3,34 @@ use quote::quote;
 use std::collections::HashSet;
 use syn::{DeriveInput, parse_macro_input};
 
-#[proc_macro_derive(Getters)]
+/// Derives a type-directed `get::<T>()` for structs whose fields all have distinct types.
+///
+/// Add `#[getters(try_get)]` to also generate `try_get::<T>() -> Option<&T>`, which returns
+/// `None` for a type that isn't a field instead of failing to compile. It trades `get`'s
+/// compile-time check for a runtime `Any` comparison against each field, so a mistyped lookup
+/// only shows up as `None` at runtime. It's opt-in so structs that don't need it keep the strict
+/// API and don't get an unused `pub(crate)` method flagged by `dead_code`.
+#[proc_macro_derive(Getters, attributes(getters))]
 pub fn derive_getters(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
 
     let name = &input.ident;
 
+    let mut with_try_get = false;
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("getters")) {
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("try_get") {
+                with_try_get = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown getters option, expected `try_get`"))
+            }
+        });
+        if let Err(err) = parsed {
+            return err.to_compile_error().into();
+        }
+    }
+
     let fields = match input.data {
         syn::Data::Struct(ref data) => &data.fields,
         _ => panic!("Getters can only be derived for structs"),
@@ -31,6 +53,27 @@ pub fn derive_getters(input: TokenStream) -> TokenStream {
         });
     }
 
+    // Runtime lookup for `try_get`, checked in declaration order so the first field of a type wins
+    let try_get = with_try_get.then(|| {
+        let any_lookups = fields.iter().map(|field| {
+            let ident = field.ident.as_ref().expect("Expected named fields");
+            quote! {
+                if let Some(value) = (&self.#ident as &dyn ::std::any::Any).downcast_ref::<T>() {
+                    return Some(value);
+                }
+            }
+        });
+        quote! {
+            impl #name {
+                /// Like `get`, but returns `None` for a type that isn't a field.
+                pub(crate) fn try_get<T: 'static>(&self) -> Option<&T> {
+                    #(#any_lookups)*
+                    None
+                }
+            }
+        }
+    });
+
     // Collect unique field types for Gettable trait implementations
     let mut unique_types = HashSet::new();
     let mut gettable_impls = Vec::new();
@@ -68,6 +111,8 @@ pub fn derive_getters(input: TokenStream) -> TokenStream {
             }
         }
 
+        #try_get
+
 
         #(#trait_impls)*
     };
@@ -0,0 +1,34 @@
+#[derive(macros::Getters)]
+#[getters(try_get)]
+struct Services {
+    name: String,
+    port: u16,
+}
+
+fn services() -> Services {
+    Services {
+        name: "web".to_string(),
+        port: 8065,
+    }
+}
+
+#[test]
+fn get_returns_field_by_type() {
+    let services = services();
+    assert_eq!(services.get::<String>(), "web");
+    assert_eq!(*services.get::<u16>(), 8065);
+}
+
+#[test]
+fn try_get_finds_present_types() {
+    let services = services();
+    assert_eq!(services.try_get::<String>().map(String::as_str), Some("web"));
+    assert_eq!(services.try_get::<u16>(), Some(&8065));
+}
+
+#[test]
+fn try_get_returns_none_for_absent_types() {
+    let services = services();
+    assert_eq!(services.try_get::<u32>(), None);
+    assert_eq!(services.try_get::<&str>(), None);
+}
@@ -1,5 +1,30 @@
 use thiserror::Error;
 
+/// Directory name used for this app under the platform config dir.
+pub const APP_DIR_NAME: &str = "mattermost-slint";
+
+pub const FIELD_USERNAME: &str = "username";
+pub const FIELD_PASSWORD: &str = "password";
+pub const FIELD_SERVER_URL: &str = "server_url";
+/// Form-level errors that aren't caused by any single field.
+pub const FIELD_GENERAL: &str = "general";
+
+/// A validation failure tied to a single form field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("Generic Error {0}")]
@@ -12,4 +37,16 @@ pub enum Error {
     UiUpgradeFailed,
     #[error("Channel Error")]
     ChannelError,
+    #[error("Backpressure Error")]
+    Backpressure,
+    #[error("IO Error: {0}")]
+    IoError(std::io::Error),
+    #[error("Serialization Error: {0}")]
+    SerializationError(serde_json::Error),
+    #[error("Request Error [{0}] {1}")]
+    RequestError(uuid::Uuid, String),
+    #[error("Unsupported Version Error {0}")]
+    UnsupportedVersion(u32),
+    #[error("Validation Error [{0}] {1:?}")]
+    Validation(uuid::Uuid, Vec<FieldError>),
 }
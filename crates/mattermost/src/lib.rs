@@ -18,8 +18,13 @@ pub async fn run() -> Result<(), crate::Error> {
     let ui = Main::new().map_err(crate::Error::SlintError)?;
 
     let app_services = crate::services::initialize(ui.as_weak()).await?;
+    if std::env::var_os("MATTERMOST_MOCK").is_some() {
+        app_services.api().web.set_mode(crate::services::ServiceMode::Mock)?;
+    }
     let _app_view_models = crate::viewmodels::initialize(ui.as_weak(), app_services.api().clone()).await?;
 
     ui.run().map_err(crate::Error::SlintError)?;
+
+    app_services.shutdown().await;
     Ok(())
 }
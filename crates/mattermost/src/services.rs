@@ -1,5 +1,19 @@
 use slint::Weak;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::task::TaskTracker;
+
+/// How long `Services::shutdown` waits for service loops before giving up on them.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+mod channel;
+pub use channel::*;
+
+mod supervisor;
+pub use supervisor::*;
+
+mod migrations;
+pub use migrations::*;
 
 mod nav;
 pub use nav::*;
@@ -10,11 +24,15 @@ pub use web::*;
 mod events;
 pub use events::*;
 
+mod auth;
+pub use auth::*;
+
 #[derive(Debug, Clone, macros::Getters)]
 pub struct ServicesApi {
     pub navigation: NavigationApi,
     pub events: EventsApi,
     pub web: WebApi,
+    pub auth: AuthApi,
 }
 
 impl ServicesApi {
@@ -23,6 +41,7 @@ impl ServicesApi {
             navigation: NavigationApi::new(),
             events: EventsApi::new(),
             web: WebApi::new(),
+            auth: AuthApi::new(),
         }
     }
 }
@@ -32,21 +51,56 @@ pub struct Services {
     navigation: NavigationService,
     events: EventsService,
     web: WebService,
+    auth: AuthService,
     api: ServicesApi,
+    tasks: TaskTracker,
 }
 
 impl Services {
     pub fn api(&self) -> &ServicesApi {
         &self.api
     }
+
+    /// Signals every service loop to stop and waits, up to `SHUTDOWN_TIMEOUT`, for them to
+    /// finish the commands already queued.
+    pub async fn shutdown(&self) {
+        self.api.navigation.shutdown();
+        self.api.events.shutdown();
+        self.api.web.shutdown();
+        self.api.auth.shutdown();
+
+        self.tasks.close();
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, self.tasks.wait()).await.is_err() {
+            log::warn!("Service loops still busy after {:?}, exiting anyway", SHUTDOWN_TIMEOUT);
+        }
+    }
 }
 
 pub async fn initialize(ui: Weak<crate::Main>) -> Result<Arc<Services>, crate::Error> {
     let api = ServicesApi::new();
+    let tasks = TaskTracker::new();
 
-    let navigation = api.navigation.clone().start_service(ui)?;
-    let events = api.events.clone().start_service()?;
-    let web = api.web.clone().start_service()?;
+    let navigation = api.navigation.clone().start_service(ui, &tasks)?;
+    let events = api.events.clone().start_service(&tasks)?;
+    let web = api.web.clone().start_service(api.navigation.clone(), &tasks)?;
+    let auth = api.auth.clone().start_service(&tasks)?;
+
+    Ok(Arc::new(Services { navigation, events, web, auth, api, tasks }))
+}
 
-    Ok(Arc::new(Services { navigation, events, web, api }))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn commands_after_shutdown_fail() {
+        let services = initialize(Weak::default()).await.unwrap();
+        services.shutdown().await;
+
+        let api = services.api();
+        assert!(matches!(api.navigation.push_loader(), Err(crate::Error::ChannelError)));
+        assert!(matches!(api.events.post(Events::Dummy, EventsData::Dummy), Err(crate::Error::ChannelError)));
+        assert!(matches!(api.web.set_mode(ServiceMode::Mock), Err(crate::Error::ChannelError)));
+        assert!(matches!(api.auth.load_credentials(|_| {}), Err(crate::Error::ChannelError)));
+    }
 }
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio_util::task::TaskTracker;
+
+use super::{CommandChannel, DEFAULT_COMMAND_CAPACITY, Migration, guard_command, migrate, payload_version, supervise};
+
+const CREDENTIALS_FILE: &str = "credentials.json";
+
+/// Upgrades for older `credentials.json` files, oldest first. Append a step whenever the format changes.
+const CREDENTIALS_MIGRATIONS: &[Migration] = &[];
+const CREDENTIALS_VERSION: u32 = CREDENTIALS_MIGRATIONS.len() as u32 + 1;
+
+/// What is kept on disk when the user asks to be remembered: enough to prefill the login form.
+/// Neither the password nor the session token is stored.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SavedCredentials {
+    pub server_url: String,
+    pub username: String,
+    #[serde(default)]
+    pub version: u32,
+}
+
+pub enum AuthApiCommand {
+    SaveCredentials(SavedCredentials, Box<dyn FnOnce(Result<(), crate::Error>) + Send>),
+    ClearCredentials(Box<dyn FnOnce(Result<(), crate::Error>) + Send>),
+    LoadCredentials(Box<dyn FnOnce(Result<Option<SavedCredentials>, crate::Error>) + Send>),
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthApi {
+    commands: CommandChannel<AuthApiCommand>,
+}
+
+pub struct AuthService {
+    pub auth: AuthApi,
+}
+
+impl Default for AuthApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuthApi {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_COMMAND_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            commands: CommandChannel::with_capacity(capacity),
+        }
+    }
+
+    fn send_command(&self, command: AuthApiCommand) -> Result<(), crate::Error> {
+        self.commands.try_send(command)
+    }
+
+    async fn send_command_async(&self, command: AuthApiCommand) -> Result<(), crate::Error> {
+        self.commands.send_async(command).await
+    }
+
+    /// Stops the service loop; commands sent afterwards fail with `Error::ChannelError`.
+    pub fn shutdown(&self) {
+        self.commands.close();
+    }
+
+    pub fn save_credentials(
+        &self,
+        credentials: SavedCredentials,
+        callback: impl FnOnce(Result<(), crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command(AuthApiCommand::SaveCredentials(credentials, Box::new(callback)))?;
+        Ok(())
+    }
+
+    pub fn clear_credentials(
+        &self,
+        callback: impl FnOnce(Result<(), crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command(AuthApiCommand::ClearCredentials(Box::new(callback)))?;
+        Ok(())
+    }
+
+    pub fn load_credentials(
+        &self,
+        callback: impl FnOnce(Result<Option<SavedCredentials>, crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command(AuthApiCommand::LoadCredentials(Box::new(callback)))?;
+        Ok(())
+    }
+
+    /// Like `save_credentials`, but waits for queue capacity instead of failing with `Error::Backpressure`.
+    pub async fn save_credentials_async(
+        &self,
+        credentials: SavedCredentials,
+        callback: impl FnOnce(Result<(), crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command_async(AuthApiCommand::SaveCredentials(credentials, Box::new(callback)))
+            .await
+    }
+
+    /// Like `clear_credentials`, but waits for queue capacity instead of failing with `Error::Backpressure`.
+    pub async fn clear_credentials_async(
+        &self,
+        callback: impl FnOnce(Result<(), crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command_async(AuthApiCommand::ClearCredentials(Box::new(callback)))
+            .await
+    }
+
+    /// Like `load_credentials`, but waits for queue capacity instead of failing with `Error::Backpressure`.
+    pub async fn load_credentials_async(
+        &self,
+        callback: impl FnOnce(Result<Option<SavedCredentials>, crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command_async(AuthApiCommand::LoadCredentials(Box::new(callback)))
+            .await
+    }
+
+    pub fn start_service(self, tasks: &TaskTracker) -> Result<AuthService, crate::Error> {
+        self.start_service_at(credentials_path(), tasks)
+    }
+
+    /// Like `start_service`, but keeps the credentials file at `path` instead of the user's config directory.
+    pub fn start_service_at(self, path: Option<PathBuf>, tasks: &TaskTracker) -> Result<AuthService, crate::Error> {
+        let auth = self.clone();
+
+        let auth_service = AuthService { auth: self };
+
+        supervise(tasks, "auth", move || {
+            let auth = auth.clone();
+            let path = path.clone();
+            async move {
+
+                while let Ok(command) = auth.commands.recv().await {
+                    guard_command("auth", async {
+                        match command {
+                            AuthApiCommand::SaveCredentials(credentials, callback) => {
+                                callback(Self::save(path.as_ref(), &credentials).await);
+                            }
+                            AuthApiCommand::ClearCredentials(callback) => {
+                                callback(Self::clear(path.as_ref()).await);
+                            }
+                            AuthApiCommand::LoadCredentials(callback) => {
+                                callback(Self::load(path.as_ref()).await);
+                            }
+                        }
+                    })
+                    .await;
+                }
+            }
+        });
+
+        Ok(auth_service)
+    }
+
+    async fn save(path: Option<&PathBuf>, credentials: &SavedCredentials) -> Result<(), crate::Error> {
+        let path = path.ok_or(crate::Error::GenericError("No config directory".to_string()))?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(crate::Error::IoError)?;
+        }
+        let credentials = SavedCredentials {
+            version: CREDENTIALS_VERSION,
+            ..credentials.clone()
+        };
+        let json = serde_json::to_vec_pretty(&credentials).map_err(crate::Error::SerializationError)?;
+        tokio::fs::write(path, json).await.map_err(crate::Error::IoError)
+    }
+
+    async fn clear(path: Option<&PathBuf>) -> Result<(), crate::Error> {
+        let Some(path) = path else {
+            return Ok(());
+        };
+        match tokio::fs::remove_file(path).await {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(crate::Error::IoError(err)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn load(path: Option<&PathBuf>) -> Result<Option<SavedCredentials>, crate::Error> {
+        let Some(path) = path else {
+            return Ok(None);
+        };
+        let bytes = match tokio::fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(crate::Error::IoError(err)),
+        };
+
+        let json = serde_json::from_slice(&bytes).map_err(crate::Error::SerializationError)?;
+        let outdated = payload_version(&json) < CREDENTIALS_VERSION;
+        match migrate(json, CREDENTIALS_MIGRATIONS) {
+            Ok(json) => {
+                let credentials = serde_json::from_value(json).map_err(crate::Error::SerializationError)?;
+                if outdated {
+                    // Rewrite right away so nothing dropped by a migration lingers on disk
+                    Self::save(Some(path), &credentials)
+                        .await
+                        .unwrap_or_else(|err| log::warn!("Failed to rewrite migrated credentials: {:?}", err));
+                }
+                Ok(Some(credentials))
+            }
+            Err(crate::Error::UnsupportedVersion(version)) => {
+                log::warn!("Ignoring saved credentials from newer version {}", version);
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+fn credentials_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(crate::APP_DIR_NAME).join(CREDENTIALS_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_credentials_path() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("mattermost-slint-{}", uuid::Uuid::new_v4()))
+            .join(CREDENTIALS_FILE)
+    }
+
+    #[tokio::test]
+    async fn save_writes_current_version() {
+        let path = temp_credentials_path();
+        let credentials = SavedCredentials {
+            server_url: "https://chat.example.com".to_string(),
+            username: "jane".to_string(),
+            ..Default::default()
+        };
+        AuthApi::save(Some(&path), &credentials).await.unwrap();
+
+        let saved: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(payload_version(&saved), CREDENTIALS_VERSION);
+        assert_eq!(saved["version"], CREDENTIALS_VERSION);
+
+        let loaded = AuthApi::load(Some(&path)).await.unwrap().unwrap();
+        assert_eq!(loaded.username, "jane");
+        assert_eq!(loaded.version, CREDENTIALS_VERSION);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[tokio::test]
+    async fn newer_credentials_are_ignored() {
+        let path = temp_credentials_path();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let newer = serde_json::json!({
+            "server_url": "https://chat.example.com",
+            "username": "jane",
+            "version": CREDENTIALS_VERSION + 1,
+        });
+        std::fs::write(&path, newer.to_string()).unwrap();
+
+        assert!(AuthApi::load(Some(&path)).await.unwrap().is_none());
+        // The file is left for the newer version that wrote it
+        assert!(path.exists());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,145 @@
+use tokio_util::sync::CancellationToken;
+
+/// Default number of commands a service queue holds before senders see backpressure.
+pub const DEFAULT_COMMAND_CAPACITY: usize = 256;
+
+/// Command queue shared between a service API handle and its background task.
+pub struct CommandChannel<C> {
+    sender: flume::Sender<C>,
+    receiver: flume::Receiver<C>,
+    shutdown: CancellationToken,
+}
+
+impl<C> Clone for CommandChannel<C> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            receiver: self.receiver.clone(),
+            shutdown: self.shutdown.clone(),
+        }
+    }
+}
+
+impl<C> std::fmt::Debug for CommandChannel<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandChannel")
+            .field("pending", &self.sender.len())
+            .field("closed", &self.is_closed())
+            .finish()
+    }
+}
+
+impl<C> Default for CommandChannel<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> CommandChannel<C> {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_COMMAND_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, receiver) = flume::bounded(capacity);
+        Self {
+            sender,
+            receiver,
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    /// Closes the queue: further sends fail, and the service loop stops once it has handled
+    /// the commands that were already queued.
+    pub fn close(&self) {
+        self.shutdown.cancel();
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Queues a command without waiting, failing with `Error::Backpressure` when the queue is full.
+    pub fn try_send(&self, command: C) -> Result<(), crate::Error> {
+        if self.is_closed() {
+            return Err(crate::Error::ChannelError);
+        }
+        self.sender.try_send(command).map_err(|err| match err {
+            flume::TrySendError::Full(_) => crate::Error::Backpressure,
+            flume::TrySendError::Disconnected(_) => crate::Error::ChannelError,
+        })
+    }
+
+    /// Queues a command, waiting for the service to free up capacity.
+    pub async fn send_async(&self, command: C) -> Result<(), crate::Error> {
+        tokio::select! {
+            biased;
+            _ = self.shutdown.cancelled() => Err(crate::Error::ChannelError),
+            result = self.sender.send_async(command) => result.map_err(|_| crate::Error::ChannelError),
+        }
+    }
+
+    /// Waits for the next command. After `close`, queued commands are still returned so their
+    /// callbacks run; `Error::ChannelError` comes once the queue is empty.
+    pub async fn recv(&self) -> Result<C, crate::Error> {
+        tokio::select! {
+            biased;
+            result = self.receiver.recv_async() => result.map_err(|_| crate::Error::ChannelError),
+            _ = self.shutdown.cancelled() => self.receiver.try_recv().map_err(|_| crate::Error::ChannelError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn delivers_commands_in_order() {
+        let channel = CommandChannel::new();
+        channel.try_send(1).unwrap();
+        channel.try_send(2).unwrap();
+
+        assert_eq!(channel.recv().await.unwrap(), 1);
+        assert_eq!(channel.recv().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn closed_channel_rejects_send_and_recv() {
+        let channel = CommandChannel::new();
+        channel.close();
+
+        assert!(channel.is_closed());
+        assert!(matches!(channel.try_send(1), Err(crate::Error::ChannelError)));
+        for _ in 0..100 {
+            assert!(matches!(channel.send_async(1).await, Err(crate::Error::ChannelError)));
+        }
+        assert!(matches!(channel.recv().await, Err(crate::Error::ChannelError)));
+    }
+
+    #[tokio::test]
+    async fn close_drains_queued_commands() {
+        let channel = CommandChannel::new();
+        channel.try_send(1).unwrap();
+        channel.close();
+
+        assert_eq!(channel.recv().await.unwrap(), 1);
+        assert!(matches!(channel.recv().await, Err(crate::Error::ChannelError)));
+    }
+
+    #[tokio::test]
+    async fn full_channel_applies_backpressure_until_drained() {
+        let channel = CommandChannel::with_capacity(1);
+        channel.try_send(1).unwrap();
+        assert!(matches!(channel.try_send(2), Err(crate::Error::Backpressure)));
+
+        let sender = channel.clone();
+        let pending = tokio::spawn(async move { sender.send_async(3).await });
+        tokio::task::yield_now().await;
+        assert!(!pending.is_finished());
+
+        assert_eq!(channel.recv().await.unwrap(), 1);
+        pending.await.unwrap().unwrap();
+        assert_eq!(channel.recv().await.unwrap(), 3);
+    }
+}
@@ -1,15 +1,94 @@
+use serde::Deserialize;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio_util::task::TaskTracker;
+
+use super::{CommandChannel, DEFAULT_COMMAND_CAPACITY, guard_callback, supervise};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Events {
     Dummy,
+    Posted,
+    Typing,
 }
 
 #[derive(Clone, Debug)]
 pub enum EventsData {
     Dummy,
+    Posted {
+        channel_id: String,
+        post: serde_json::Value,
+    },
+    Typing {
+        channel_id: String,
+        user_id: String,
+        parent_id: String,
+    },
+}
+
+// Only the fields the client reads are deserialized; everything else in a frame is skipped
+// without being built. Strings borrow from the frame unless they contain escapes.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct WsBroadcast<'a> {
+    #[serde(borrow)]
+    channel_id: Cow<'a, str>,
 }
 
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct WsData<'a> {
+    #[serde(borrow)]
+    post: Cow<'a, str>,
+    #[serde(borrow)]
+    user_id: Cow<'a, str>,
+    #[serde(borrow)]
+    parent_id: Cow<'a, str>,
+}
+
+#[derive(Deserialize)]
+struct WsFrame<'a> {
+    #[serde(borrow)]
+    event: Cow<'a, str>,
+    #[serde(default, borrow)]
+    data: WsData<'a>,
+    #[serde(default, borrow)]
+    broadcast: WsBroadcast<'a>,
+}
+
+/// Maps a websocket text frame to an event. Events the client doesn't know about yet yield
+/// `Ok(None)` so newer servers don't break older clients; malformed frames are an error.
+pub fn parse_ws_event(text: &str) -> Result<Option<(Events, EventsData)>, crate::Error> {
+    let frame: WsFrame = serde_json::from_str(text).map_err(crate::Error::SerializationError)?;
+
+    let event = match frame.event.as_ref() {
+        "posted" => {
+            // The server sends the post as a JSON document embedded in a string
+            let post = serde_json::from_str(&frame.data.post).map_err(crate::Error::SerializationError)?;
+            (
+                Events::Posted,
+                EventsData::Posted {
+                    channel_id: frame.broadcast.channel_id.into_owned(),
+                    post,
+                },
+            )
+        }
+        "typing" => (
+            Events::Typing,
+            EventsData::Typing {
+                user_id: frame.data.user_id.into_owned(),
+                parent_id: frame.data.parent_id.into_owned(),
+                channel_id: frame.broadcast.channel_id.into_owned(),
+            },
+        ),
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
+
+type Subscribers = HashMap<Events, Vec<Box<dyn Fn(&EventsData) + Send>>>;
+
 pub enum EventsApiCommand {
     Subscribe(Events, Box<dyn Fn(&EventsData) + Send>),
     Post(Events, EventsData),
@@ -17,27 +96,41 @@ pub enum EventsApiCommand {
 
 #[derive(Debug, Clone)]
 pub struct EventsApi {
-    commands: (
-        flume::Sender<EventsApiCommand>,
-        flume::Receiver<EventsApiCommand>,
-    ),
+    commands: CommandChannel<EventsApiCommand>,
 }
 
 pub struct EventsService {
     pub events: EventsApi,
 }
 
+impl Default for EventsApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EventsApi {
     pub fn new() -> Self {
-        let commands = flume::unbounded();
-        Self { commands }
+        Self::with_capacity(DEFAULT_COMMAND_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            commands: CommandChannel::with_capacity(capacity),
+        }
     }
 
     fn send_command(&self, command: EventsApiCommand) -> Result<(), crate::Error> {
-        self.commands
-            .0
-            .send(command)
-            .map_err(|_| crate::Error::ChannelError)
+        self.commands.try_send(command)
+    }
+
+    async fn send_command_async(&self, command: EventsApiCommand) -> Result<(), crate::Error> {
+        self.commands.send_async(command).await
+    }
+
+    /// Stops the service loop; commands sent afterwards fail with `Error::ChannelError`.
+    pub fn shutdown(&self) {
+        self.commands.close();
     }
 
     pub fn subscribe(
@@ -54,8 +147,15 @@ impl EventsApi {
         Ok(())
     }
 
+    /// Like `post`, but waits for queue capacity instead of failing with `Error::Backpressure`.
+    /// Meant for producers that run on the runtime, e.g. a stream of server events.
+    pub async fn post_async(&self, event: Events, data: EventsData) -> Result<(), crate::Error> {
+        self.send_command_async(EventsApiCommand::Post(event, data)).await
+    }
+
     pub fn start_service(
         self,
+        tasks: &TaskTracker,
     ) -> Result<EventsService, crate::Error> {
         let events = self.clone();
 
@@ -63,19 +163,25 @@ impl EventsApi {
             events: self,
         };
 
-        // Could also be a std::thread::spawn?
-        tokio::task::spawn(async move {
-            let mut callbacks = HashMap::<Events, Vec<Box<dyn Fn(&EventsData) + Send>>>::new();
+        // Lives outside the loop so subscriptions survive a supervisor restart
+        let subscribers = Arc::new(Mutex::new(Subscribers::new()));
 
-            while let Ok(command) = events.commands.1.recv_async().await {
-                match command {
-                    EventsApiCommand::Subscribe(event, callback) => {
-                        callbacks.entry(event).or_default().push(callback);
-                    }
-                    EventsApiCommand::Post(event, data) => {
-                        if let Some(cbs) = callbacks.get(&event) {
-                            for cb in cbs {
-                                cb(&data);
+        // Could also be a std::thread::spawn?
+        supervise(tasks, "events", move || {
+            let events = events.clone();
+            let subscribers = subscribers.clone();
+            async move {
+                while let Ok(command) = events.commands.recv().await {
+                    let mut callbacks = subscribers.lock().unwrap_or_else(PoisonError::into_inner);
+                    match command {
+                        EventsApiCommand::Subscribe(event, callback) => {
+                            callbacks.entry(event).or_default().push(callback);
+                        }
+                        EventsApiCommand::Post(event, data) => {
+                            if let Some(cbs) = callbacks.get(&event) {
+                                for cb in cbs {
+                                    guard_callback("events", || cb(&data));
+                                }
                             }
                         }
                     }
@@ -86,3 +192,78 @@ impl EventsApi {
         Ok(events_service)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_posted_event() {
+        let frame = r#"{
+            "event": "posted",
+            "data": {
+                "channel_display_name": "Town Square",
+                "post": "{\"id\":\"p1\",\"message\":\"hello\"}"
+            },
+            "broadcast": { "channel_id": "c1" },
+            "seq": 3
+        }"#;
+
+        let Some((Events::Posted, EventsData::Posted { channel_id, post })) =
+            parse_ws_event(frame).unwrap()
+        else {
+            panic!("expected a posted event");
+        };
+        assert_eq!(channel_id, "c1");
+        assert_eq!(post["message"], "hello");
+    }
+
+    #[test]
+    fn parses_typing_event() {
+        let frame = r#"{
+            "event": "typing",
+            "data": { "parent_id": "", "user_id": "u1" },
+            "broadcast": { "channel_id": "c1" }
+        }"#;
+
+        let Some((Events::Typing, EventsData::Typing { channel_id, user_id, parent_id })) =
+            parse_ws_event(frame).unwrap()
+        else {
+            panic!("expected a typing event");
+        };
+        assert_eq!(channel_id, "c1");
+        assert_eq!(user_id, "u1");
+        assert_eq!(parent_id, "");
+    }
+
+    #[test]
+    fn unknown_event_is_skipped() {
+        let frame = r#"{ "event": "emoji_added", "data": {}, "broadcast": {} }"#;
+        assert!(parse_ws_event(frame).unwrap().is_none());
+    }
+
+    #[test]
+    fn malformed_frames_are_errors() {
+        for frame in ["not json", r#"{ "data": {} }"#, r#"{ "event": "posted", "data": { "post": "{" } }"#] {
+            assert!(
+                matches!(parse_ws_event(frame), Err(crate::Error::SerializationError(_))),
+                "{frame:?} should fail to parse"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn panicking_subscriber_does_not_stop_the_loop() {
+        let tasks = TaskTracker::new();
+        let events = EventsApi::new();
+        events.clone().start_service(&tasks).unwrap();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        events.subscribe(Events::Dummy, |_| panic!("subscriber failed")).unwrap();
+        events.post(Events::Dummy, EventsData::Dummy).unwrap();
+        events.subscribe(Events::Dummy, move |_| tx.send(()).unwrap()).unwrap();
+        events.post(Events::Dummy, EventsData::Dummy).unwrap();
+
+        rx.recv().await.unwrap();
+    }
+}
@@ -0,0 +1,76 @@
+use serde_json::Value;
+
+/// Upgrades one payload version to the next.
+pub type Migration = fn(Value) -> Value;
+
+const VERSION_KEY: &str = "version";
+
+/// Version of a persisted payload. Payloads written before versioning have none (or 0) and count as 1.
+pub fn payload_version(json: &Value) -> u32 {
+    json.get(VERSION_KEY)
+        .and_then(Value::as_u64)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Runs the migrations needed to bring `json` from its version to the latest one.
+/// `steps[n]` upgrades version `n + 1` to `n + 2`, so the latest version is `steps.len() + 1`.
+/// Payloads from a newer version than that fail with `Error::UnsupportedVersion`.
+pub fn migrate(mut json: Value, steps: &[Migration]) -> Result<Value, crate::Error> {
+    let current_version = steps.len() as u32 + 1;
+    let from_version = payload_version(&json);
+    if from_version > current_version {
+        return Err(crate::Error::UnsupportedVersion(from_version));
+    }
+
+    for (index, step) in steps.iter().enumerate().skip(from_version as usize - 1) {
+        log::info!("Migrating persisted payload from version {} to {}", index + 1, index + 2);
+        json = step(json);
+    }
+
+    if let Value::Object(map) = &mut json {
+        map.insert(VERSION_KEY.to_string(), current_version.into());
+    }
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn add_step(mut json: Value) -> Value {
+        json["steps"] = (json["steps"].as_u64().unwrap_or(0) + 1).into();
+        json
+    }
+
+    const STEPS: &[Migration] = &[add_step, add_step];
+
+    #[test]
+    fn unversioned_payload_runs_every_step() {
+        let json = migrate(json!({}), STEPS).unwrap();
+        assert_eq!(json, json!({ "steps": 2, "version": 3 }));
+    }
+
+    #[test]
+    fn version_zero_counts_as_one() {
+        let json = migrate(json!({ "version": 0 }), STEPS).unwrap();
+        assert_eq!(json, json!({ "steps": 2, "version": 3 }));
+    }
+
+    #[test]
+    fn only_missing_steps_run() {
+        let json = migrate(json!({ "version": 2 }), STEPS).unwrap();
+        assert_eq!(json, json!({ "steps": 1, "version": 3 }));
+
+        let json = migrate(json!({ "version": 3 }), STEPS).unwrap();
+        assert_eq!(json, json!({ "version": 3 }));
+    }
+
+    #[test]
+    fn newer_payload_is_unsupported() {
+        let result = migrate(json!({ "version": 4 }), STEPS);
+        assert!(matches!(result, Err(crate::Error::UnsupportedVersion(4))));
+    }
+}
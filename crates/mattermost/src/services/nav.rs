@@ -1,15 +1,71 @@
 use slint::ComponentHandle;
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio_util::task::TaskTracker;
+
+use super::{CommandChannel, DEFAULT_COMMAND_CAPACITY, guard_callback, supervise};
 
 pub enum NavigationApiCommand {
-    UpdateLoader(bool),
+    NavigateTo(crate::NavigationTarget),
+    PushLoader,
+    PopLoader,
+    SetConnectionState(crate::ConnectionState),
+}
+
+/// Reference count behind `push_loader`/`pop_loader`.
+#[derive(Debug, Default)]
+struct LoaderDepth(usize);
+
+impl LoaderDepth {
+    /// Returns the new loader visibility when this push changes it.
+    fn push(&mut self) -> Option<bool> {
+        self.0 += 1;
+        (self.0 == 1).then_some(true)
+    }
+
+    /// Returns the new loader visibility when this pop changes it.
+    fn pop(&mut self) -> Option<bool> {
+        if self.0 == 0 {
+            log::warn!("pop_loader called without a matching push_loader");
+            return None;
+        }
+        self.0 -= 1;
+        (self.0 == 0).then_some(false)
+    }
+}
+
+/// What the navigation loop remembers between commands.
+#[derive(Debug, Default)]
+struct NavState {
+    current_target: crate::NavigationTarget,
+    loader_depth: LoaderDepth,
+}
+
+impl NavState {
+    /// Moves to `target` and returns it when the page actually changes. Repeated and
+    /// disallowed moves leave the state alone.
+    fn navigate(&mut self, target: crate::NavigationTarget) -> Option<crate::NavigationTarget> {
+        if target == self.current_target {
+            return None;
+        }
+        if !can_navigate(self.current_target, target) {
+            log::warn!("Ignoring navigation from {:?} to {:?}", self.current_target, target);
+            return None;
+        }
+        self.current_target = target;
+        Some(target)
+    }
+}
+
+/// Whether the UI may move from one page to another. Main and Settings need a logged in
+/// user, so they are only entered through Login; returning to Login is always allowed.
+fn can_navigate(from: crate::NavigationTarget, to: crate::NavigationTarget) -> bool {
+    use crate::NavigationTarget::*;
+    matches!((from, to), (_, Login) | (Login, Main) | (Main, Settings) | (Settings, Main))
 }
 
 #[derive(Debug, Clone)]
 pub struct NavigationApi {
-    commands: (
-        flume::Sender<NavigationApiCommand>,
-        flume::Receiver<NavigationApiCommand>,
-    ),
+    pub(super) commands: CommandChannel<NavigationApiCommand>,
 }
 
 pub struct NavigationService {
@@ -17,32 +73,78 @@ pub struct NavigationService {
     pub ui: slint::Weak<crate::Main>,
 }
 
+impl Default for NavigationApi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl NavigationApi {
     pub fn new() -> Self {
-        let commands = flume::unbounded();
-        Self { commands }
+        Self::with_capacity(DEFAULT_COMMAND_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            commands: CommandChannel::with_capacity(capacity),
+        }
     }
 
     fn send_command(&self, command: NavigationApiCommand) -> Result<(), crate::Error> {
-        self.commands
-            .0
-            .send(command)
-            .map_err(|_| crate::Error::ChannelError)
+        self.commands.try_send(command)
+    }
+
+    async fn send_command_async(&self, command: NavigationApiCommand) -> Result<(), crate::Error> {
+        self.commands.send_async(command).await
+    }
+
+    /// Stops the service loop; commands sent afterwards fail with `Error::ChannelError`.
+    pub fn shutdown(&self) {
+        self.commands.close();
+    }
+
+    pub fn navigate_to(&self, target: crate::NavigationTarget) -> Result<(), crate::Error> {
+        self.send_command(NavigationApiCommand::NavigateTo(target))?;
+        Ok(())
+    }
+
+    /// Shows the loading popup. It stays visible until every push has a matching `pop_loader`.
+    pub fn push_loader(&self) -> Result<(), crate::Error> {
+        self.send_command(NavigationApiCommand::PushLoader)?;
+        Ok(())
+    }
+
+    pub fn pop_loader(&self) -> Result<(), crate::Error> {
+        self.send_command(NavigationApiCommand::PopLoader)?;
+        Ok(())
     }
 
-    pub fn update_loader(
+    pub fn set_connection_state(
         &self,
-        show: bool,
+        connection_state: crate::ConnectionState,
     ) -> Result<(), crate::Error> {
-        self.send_command(NavigationApiCommand::UpdateLoader(
-            show,
+        self.send_command(NavigationApiCommand::SetConnectionState(
+            connection_state,
         ))?;
         Ok(())
     }
 
+    /// Like `set_connection_state`, but waits for queue capacity instead of failing
+    /// with `Error::Backpressure`, so background services never drop a state change.
+    pub async fn set_connection_state_async(
+        &self,
+        connection_state: crate::ConnectionState,
+    ) -> Result<(), crate::Error> {
+        self.send_command_async(NavigationApiCommand::SetConnectionState(
+            connection_state,
+        ))
+        .await
+    }
+
     pub fn start_service(
         self,
         ui: slint::Weak<crate::Main>,
+        tasks: &TaskTracker,
     ) -> Result<NavigationService, crate::Error> {
         let navigation = self.clone();
 
@@ -50,24 +152,142 @@ impl NavigationApi {
             navigation: self,
             ui: ui.clone(),
         };
+        // Lives outside the loop so a restarted loop still matches what the UI shows
+        let state = Arc::new(Mutex::new(NavState::default()));
+
         // Could also be a std::thread::spawn?
-        tokio::task::spawn(async move {
-            while let Ok(command) = navigation.commands.1.recv_async().await {
-                match command {
-                    NavigationApiCommand::UpdateLoader(show) => {
-                        ui.upgrade_in_event_loop(move |ui| {
-                            let store = ui.global::<crate::NavStore>();
-                            store.set_currentPopup(if show {
-                                crate::CurrentPopup::Loading
-                            } else {
-                                crate::CurrentPopup::None
-                            });
-                        })
-                        .ok();
-                    }
+        supervise(tasks, "navigation", move || {
+            let navigation = navigation.clone();
+            let ui = ui.clone();
+            let state = state.clone();
+            async move {
+                while let Ok(command) = navigation.commands.recv().await {
+                    let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                    guard_callback("navigation", || {
+                        Self::handle_command(&ui, &mut state, command);
+                    });
                 }
             }
         });
         Ok(navigation_service)
     }
+
+    fn handle_command(
+        ui: &slint::Weak<crate::Main>,
+        state: &mut NavState,
+        command: NavigationApiCommand,
+    ) {
+        match command {
+            NavigationApiCommand::NavigateTo(target) => {
+                let Some(target) = state.navigate(target) else {
+                    return;
+                };
+                ui.upgrade_in_event_loop(move |ui| {
+                    let store = ui.global::<crate::NavStore>();
+                    store.set_currentTarget(target);
+                })
+                .ok();
+            }
+            NavigationApiCommand::PushLoader | NavigationApiCommand::PopLoader => {
+                let changed = if matches!(command, NavigationApiCommand::PushLoader) {
+                    state.loader_depth.push()
+                } else {
+                    state.loader_depth.pop()
+                };
+                let Some(show) = changed else {
+                    return;
+                };
+                ui.upgrade_in_event_loop(move |ui| {
+                    let store = ui.global::<crate::NavStore>();
+                    store.set_currentPopup(if show {
+                        crate::CurrentPopup::Loading
+                    } else {
+                        crate::CurrentPopup::None
+                    });
+                })
+                .ok();
+            }
+            NavigationApiCommand::SetConnectionState(connection_state) => {
+                ui.upgrade_in_event_loop(move |ui| {
+                    let store = ui.global::<crate::NavStore>();
+                    store.set_connectionState(connection_state);
+                })
+                .ok();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::NavigationTarget;
+
+    #[tokio::test]
+    async fn navigate_to_queues_command() {
+        let navigation = NavigationApi::new();
+        navigation.navigate_to(NavigationTarget::Main).unwrap();
+
+        assert!(matches!(
+            navigation.commands.recv().await.unwrap(),
+            NavigationApiCommand::NavigateTo(NavigationTarget::Main)
+        ));
+    }
+
+    #[test]
+    fn login_leads_to_main() {
+        assert!(can_navigate(NavigationTarget::Login, NavigationTarget::Main));
+        assert!(can_navigate(NavigationTarget::Main, NavigationTarget::Settings));
+        assert!(can_navigate(NavigationTarget::Settings, NavigationTarget::Main));
+        assert!(can_navigate(NavigationTarget::Settings, NavigationTarget::Login));
+        assert!(can_navigate(NavigationTarget::Main, NavigationTarget::Login));
+    }
+
+    #[test]
+    fn settings_needs_login() {
+        assert!(!can_navigate(NavigationTarget::Login, NavigationTarget::Settings));
+    }
+
+    #[test]
+    fn navigates_from_login_through_main_and_settings() {
+        let mut state = NavState::default();
+        assert_eq!(state.current_target, NavigationTarget::Login);
+
+        assert_eq!(state.navigate(NavigationTarget::Main), Some(NavigationTarget::Main));
+        assert_eq!(state.navigate(NavigationTarget::Settings), Some(NavigationTarget::Settings));
+        assert_eq!(state.navigate(NavigationTarget::Main), Some(NavigationTarget::Main));
+        assert_eq!(state.current_target, NavigationTarget::Main);
+    }
+
+    #[test]
+    fn rejected_and_repeated_moves_keep_state() {
+        let mut state = NavState::default();
+
+        assert_eq!(state.navigate(NavigationTarget::Settings), None);
+        assert_eq!(state.current_target, NavigationTarget::Login);
+        assert_eq!(state.navigate(NavigationTarget::Login), None);
+        assert_eq!(state.current_target, NavigationTarget::Login);
+    }
+
+    #[test]
+    fn loader_stays_visible_until_last_pop() {
+        let mut depth = LoaderDepth::default();
+
+        assert_eq!(depth.push(), Some(true));
+        assert_eq!(depth.push(), None);
+        assert_eq!(depth.pop(), None);
+        assert_eq!(depth.push(), None);
+        assert_eq!(depth.pop(), None);
+        assert_eq!(depth.pop(), Some(false));
+    }
+
+    #[test]
+    fn unmatched_pop_is_ignored() {
+        let mut depth = LoaderDepth::default();
+
+        assert_eq!(depth.pop(), None);
+        assert_eq!(depth.push(), Some(true));
+        assert_eq!(depth.pop(), Some(false));
+    }
 }
@@ -0,0 +1,96 @@
+use futures::FutureExt;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::Duration;
+use tokio_util::task::TaskTracker;
+
+/// Delay before a crashed service loop is started again, so a loop that
+/// panics immediately can't spin.
+const RESTART_DELAY: Duration = Duration::from_millis(500);
+
+/// Runs a service loop on `tasks` and starts a fresh one from `make_loop`
+/// whenever the previous one dies from a panic. A loop that returns normally
+/// (its command channel was closed) is not restarted.
+pub fn supervise<F, Fut>(tasks: &TaskTracker, service: &'static str, make_loop: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let tracker = tasks.clone();
+    tasks.spawn(async move {
+        loop {
+            match tracker.spawn(make_loop()).await {
+                Err(err) if err.is_panic() => {
+                    log::error!("{} service loop panicked, restarting", service);
+                    tokio::time::sleep(RESTART_DELAY).await;
+                }
+                _ => break,
+            }
+        }
+    });
+}
+
+/// Handles a single command, logging a panic instead of letting it unwind
+/// through the service loop.
+pub async fn guard_command(service: &'static str, handler: impl Future<Output = ()>) {
+    if let Err(panic) = AssertUnwindSafe(handler).catch_unwind().await {
+        log::error!("{} service command panicked: {}", service, panic_message(&*panic));
+    }
+}
+
+/// Synchronous counterpart of `guard_command` for handlers that never await.
+pub fn guard_callback(service: &'static str, handler: impl FnOnce()) {
+    if let Err(panic) = std::panic::catch_unwind(AssertUnwindSafe(handler)) {
+        log::error!("{} service command panicked: {}", service, panic_message(&*panic));
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::CommandChannel;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::oneshot;
+
+    #[tokio::test(start_paused = true)]
+    async fn restarts_loop_after_panic() {
+        let tasks = TaskTracker::new();
+        let commands = CommandChannel::<oneshot::Sender<usize>>::new();
+        let starts = Arc::new(AtomicUsize::new(0));
+
+        let loop_commands = commands.clone();
+        let loop_starts = starts.clone();
+        supervise(&tasks, "test", move || {
+            let commands = loop_commands.clone();
+            let start = loop_starts.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                if start == 1 {
+                    panic!("first loop dies");
+                }
+                while let Ok(reply) = commands.recv().await {
+                    reply.send(start).unwrap();
+                }
+            }
+        });
+
+        let (tx, rx) = oneshot::channel();
+        commands.try_send(tx).unwrap();
+        assert_eq!(rx.await.unwrap(), 2);
+
+        // A loop that ends because its channel closed is not restarted
+        commands.close();
+        tasks.close();
+        tasks.wait().await;
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+    }
+}
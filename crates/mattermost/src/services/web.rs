@@ -1,6 +1,7 @@
 pub mod types;
 pub mod api;
 pub mod service;
+mod cache;
 
 pub use types::*;
 pub use api::*;
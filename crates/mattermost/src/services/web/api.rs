@@ -1,11 +1,9 @@
 use super::types::*;
+use crate::services::{CommandChannel, DEFAULT_COMMAND_CAPACITY};
 
 #[derive(Debug, Clone)]
 pub struct WebApi {
-    pub(super) commands: (
-        flume::Sender<WebApiCommand>,
-        flume::Receiver<WebApiCommand>,
-    ),
+    pub(super) commands: CommandChannel<WebApiCommand>,
 }
 
 impl Default for WebApi {
@@ -16,22 +14,33 @@ impl Default for WebApi {
 
 impl WebApi {
     pub fn new() -> Self {
-        let commands = flume::unbounded();
-        Self { commands }
+        Self::with_capacity(DEFAULT_COMMAND_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            commands: CommandChannel::with_capacity(capacity),
+        }
     }
 
     fn send_command(&self, command: WebApiCommand) -> Result<(), crate::Error> {
-        self.commands
-            .0
-            .send(command)
-            .map_err(|_| crate::Error::ChannelError)
+        self.commands.try_send(command)
+    }
+
+    async fn send_command_async(&self, command: WebApiCommand) -> Result<(), crate::Error> {
+        self.commands.send_async(command).await
+    }
+
+    /// Stops the service loop; commands sent afterwards fail with `Error::ChannelError`.
+    pub fn shutdown(&self) {
+        self.commands.close();
     }
 
     pub fn set_config(
         &self,
         base_url: &str,
         api_version: &str,
-        callback: impl FnOnce() + 'static + Send,
+        callback: impl FnOnce(Result<(), crate::Error>) + 'static + Send,
     ) -> Result<(), crate::Error> {
         self.send_command(WebApiCommand::SetConfig(
             base_url.to_string(),
@@ -41,6 +50,104 @@ impl WebApi {
         Ok(())
     }
 
+    pub fn set_mode(&self, mode: ServiceMode) -> Result<(), crate::Error> {
+        self.send_command(WebApiCommand::SetMode(mode))?;
+        Ok(())
+    }
+
+    /// Checks that `base_url` answers like a Mattermost server, without touching the stored config.
+    pub fn ping(
+        &self,
+        base_url: &str,
+        callback: impl FnOnce(Result<PingResult, crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command(WebApiCommand::Ping(base_url.to_string(), Box::new(callback)))?;
+        Ok(())
+    }
+
+    /// Fetches every page of a paginated list endpoint, stopping at the first short page.
+    /// `path` is relative to the API root, e.g. `teams/{team_id}/channels`.
+    pub fn fetch_all<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        per_page: usize,
+        callback: impl FnOnce(Result<Vec<T>, crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command(Self::fetch_all_command(path, per_page, callback))?;
+        Ok(())
+    }
+
+    /// Like `fetch_all`, but waits for queue capacity instead of failing with `Error::Backpressure`.
+    pub async fn fetch_all_async<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        per_page: usize,
+        callback: impl FnOnce(Result<Vec<T>, crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command_async(Self::fetch_all_command(path, per_page, callback))
+            .await
+    }
+
+    fn fetch_all_command<T: serde::de::DeserializeOwned>(
+        path: &str,
+        per_page: usize,
+        callback: impl FnOnce(Result<Vec<T>, crate::Error>) + 'static + Send,
+    ) -> WebApiCommand {
+        WebApiCommand::FetchAll(
+            path.to_string(),
+            per_page,
+            Box::new(move |result| {
+                callback(result.and_then(|items| {
+                    items
+                        .into_iter()
+                        .map(serde_json::from_value)
+                        .collect::<Result<Vec<T>, _>>()
+                        .map_err(crate::Error::SerializationError)
+                }));
+            }),
+        )
+    }
+
+    pub fn get_team_channels(
+        &self,
+        team_id: &str,
+        callback: impl FnOnce(Result<Vec<Channel>, crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.fetch_all(&format!("teams/{team_id}/channels"), 100, callback)
+    }
+
+    /// Fetches a user's profile picture. `last_picture_update` comes from `User`
+    /// and lets unchanged pictures be served from the in-memory cache.
+    pub fn get_user_image(
+        &self,
+        user_id: &str,
+        last_picture_update: i64,
+        callback: impl FnOnce(Result<UserImage, crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command(WebApiCommand::GetUserImage(
+            user_id.to_string(),
+            last_picture_update,
+            Box::new(callback),
+        ))?;
+        Ok(())
+    }
+
+    /// Like `get_user_image`, but waits for queue capacity instead of failing with
+    /// `Error::Backpressure`, e.g. when loading the pictures of a long member list.
+    pub async fn get_user_image_async(
+        &self,
+        user_id: &str,
+        last_picture_update: i64,
+        callback: impl FnOnce(Result<UserImage, crate::Error>) + 'static + Send,
+    ) -> Result<(), crate::Error> {
+        self.send_command_async(WebApiCommand::GetUserImage(
+            user_id.to_string(),
+            last_picture_update,
+            Box::new(callback),
+        ))
+        .await
+    }
+
     pub fn user_login(
         &self,
         login_data: LoginData,
@@ -0,0 +1,36 @@
+use std::collections::VecDeque;
+
+/// Small least-recently-used cache. Lookups are linear, so keep `capacity` modest.
+pub(super) struct LruCache<K, V> {
+    entries: VecDeque<(K, V)>,
+    capacity: usize,
+}
+
+impl<K: PartialEq, V: Clone> LruCache<K, V> {
+    pub(super) fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub(super) fn get(&mut self, key: &K) -> Option<V> {
+        let index = self.entries.iter().position(|(entry_key, _)| entry_key == key)?;
+        let entry = self.entries.remove(index)?;
+        let value = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(value)
+    }
+
+    pub(super) fn insert(&mut self, key: K, value: V) {
+        if let Some(index) = self.entries.iter().position(|(entry_key, _)| *entry_key == key) {
+            self.entries.remove(index);
+        }
+        self.entries.push_front((key, value));
+        self.entries.truncate(self.capacity);
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
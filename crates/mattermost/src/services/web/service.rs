@@ -1,29 +1,68 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::task::TaskTracker;
+use tracing::Instrument;
+
 use super::types::*;
 use super::api::WebApi;
+use super::cache::LruCache;
+use crate::services::{NavigationApi, guard_command, supervise};
+
+/// Upper bound for `per_page` accepted by the Mattermost API.
+const MAX_PER_PAGE: usize = 200;
+/// Safety cap so a misbehaving server can't keep a paginated fetch running forever.
+const MAX_FETCH_PAGES: usize = 100;
+/// Number of user profile pictures kept in memory.
+const USER_IMAGE_CACHE_SIZE: usize = 128;
+/// Time allowed to establish a connection to the server.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time allowed for a whole request, so an unresponsive server can't stall the service loop.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub struct WebService {
     pub web: WebApi,
 }
 
 impl WebApi {
-    pub fn start_service(self) -> Result<WebService, crate::Error> {
+    pub fn start_service(
+        self,
+        navigation: NavigationApi,
+        tasks: &TaskTracker,
+    ) -> Result<WebService, crate::Error> {
         let web = self.clone();
         let web_service = WebService { web: self };
 
-        tokio::task::spawn(async move {
-            let mut config = WebConfig::default();
+        // Lives outside the loop so a restart keeps the mode, server config and session
+        let state = Arc::new(tokio::sync::Mutex::new(WebState {
+            mode: ServiceMode::default(),
+            client: http_client(),
+            token: None,
+            user_images: LruCache::new(USER_IMAGE_CACHE_SIZE),
+            config: WebConfig::default(),
+            connection_state: crate::ConnectionState::Disconnected,
+            navigation,
+        }));
 
-            while let Ok(command) = web.commands.1.recv_async().await {
-                match command {
-                    WebApiCommand::SetConfig(base_url, api_version, callback) => {
-                        config.base_url = base_url;
-                        config.api_version = api_version;
-                        callback();
-                    }
-                    WebApiCommand::UserLogin(login_data, callback) => {
-                        let response = Self::mock_login_response(&login_data).await;
-                        callback(Ok(response));
-                    }
+        supervise(tasks, "web", move || {
+            let web = web.clone();
+            let state = state.clone();
+            async move {
+                while let Ok(command) = web.commands.recv().await {
+                    let mut state = state.lock().await;
+                    let request_id = uuid::Uuid::new_v4();
+                    let span = tracing::info_span!(
+                        "web_command",
+                        %request_id,
+                        command = <&'static str>::from(&command),
+                        base_url = %state.config.base_url,
+                    );
+                    guard_command(
+                        "web",
+                        Self::handle_command(&mut state, command, request_id).instrument(span.clone()),
+                    )
+                    .await;
+                    // SetConfig may have changed the URL while the span still holds the old one
+                    span.record("base_url", tracing::field::display(&state.config.base_url));
                 }
             }
         });
@@ -31,7 +70,216 @@ impl WebApi {
         Ok(web_service)
     }
 
-    async fn mock_login_response(login_data: &LoginData) -> LoginResponse {
+    async fn handle_command(state: &mut WebState, command: WebApiCommand, request_id: uuid::Uuid) {
+        tracing::debug!("Handling web command");
+        match command {
+            WebApiCommand::SetMode(mode) => {
+                tracing::info!("Switching web service mode to {:?}", mode);
+                state.mode = mode;
+                state.user_images.clear();
+            }
+            WebApiCommand::SetConfig(base_url, api_version, callback) => {
+                match normalize_base_url(&base_url) {
+                    Ok(base_url) => {
+                        state.config.base_url = base_url;
+                        state.config.api_version = api_version;
+                        state.user_images.clear();
+                        state.set_connection_state(crate::ConnectionState::Connecting).await;
+                        callback(Ok(()));
+                    }
+                    Err(err) => {
+                        tracing::warn!("[{}] Rejected base url: {:?}", request_id, err);
+                        state.set_connection_state(crate::ConnectionState::Disconnected).await;
+                        callback(Err(crate::Error::RequestError(request_id, err.to_string())));
+                    }
+                }
+            }
+            WebApiCommand::UserLogin(login_data, callback) => {
+                state.set_connection_state(crate::ConnectionState::Connecting).await;
+                let response = match state.mode {
+                    ServiceMode::Mock => Ok(Self::mock_login_response(&login_data, request_id).await),
+                    ServiceMode::Live => Self::login(state, &login_data, request_id).await,
+                };
+                state.track_result(&response).await;
+                state.token = response.as_ref().ok().map(|response| response.token.clone());
+                // Pictures fetched under the previous session may not be visible to the new one
+                state.user_images.clear();
+                callback(response);
+            }
+            WebApiCommand::Ping(base_url, callback) => {
+                let result = match normalize_base_url(&base_url) {
+                    Ok(_) if state.mode == ServiceMode::Mock => Ok(PingResult {
+                        reachable: true,
+                        status: Some("OK".to_string()),
+                    }),
+                    Ok(base_url) => Ok(Self::system_ping(state, &base_url, request_id).await),
+                    Err(err) => Err(err),
+                };
+                if !matches!(result, Ok(PingResult { reachable: true, .. })) {
+                    state.set_connection_state(crate::ConnectionState::Disconnected).await;
+                }
+                callback(result);
+            }
+            WebApiCommand::GetUserImage(user_id, last_picture_update, callback) => {
+                if state.mode == ServiceMode::Mock {
+                    callback(Ok(UserImage::Default));
+                    return;
+                }
+
+                let key = (user_id, last_picture_update);
+                if let Some(image) = state.user_images.get(&key) {
+                    tracing::debug!("Serving user image from cache");
+                    callback(Ok(image));
+                    return;
+                }
+
+                let result = Self::user_image(state, &key.0, request_id).await;
+                if let Ok(image) = &result {
+                    state.user_images.insert(key, image.clone());
+                }
+                state.track_result(&result).await;
+                callback(result);
+            }
+            WebApiCommand::FetchAll(path, per_page, callback) => {
+                let result = match state.mode {
+                    ServiceMode::Mock => Ok(Vec::new()),
+                    ServiceMode::Live => Self::fetch_all_pages(state, &path, per_page, request_id).await,
+                };
+                state.track_result(&result).await;
+                callback(result);
+            }
+        }
+    }
+
+    async fn user_image(
+        state: &WebState,
+        user_id: &str,
+        request_id: uuid::Uuid,
+    ) -> Result<UserImage, crate::Error> {
+        let request_error = |err: reqwest::Error| crate::Error::RequestError(request_id, err.to_string());
+
+        let mut request = state.client.get(state.config.endpoint(&format!("users/{user_id}/image")));
+        if let Some(token) = &state.token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await.map_err(request_error)?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(UserImage::Default);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(crate::Error::RequestError(request_id, format!("{status}: {body}")));
+        }
+
+        let bytes = response.bytes().await.map_err(request_error)?;
+        Ok(UserImage::Image(bytes.as_ref().into()))
+    }
+
+    async fn fetch_all_pages(
+        state: &WebState,
+        path: &str,
+        per_page: usize,
+        request_id: uuid::Uuid,
+    ) -> Result<Vec<serde_json::Value>, crate::Error> {
+        let request_error = |err: reqwest::Error| crate::Error::RequestError(request_id, err.to_string());
+        let per_page = per_page.clamp(1, MAX_PER_PAGE);
+        let mut items = Vec::new();
+
+        for page in 0..MAX_FETCH_PAGES {
+            let mut request = state
+                .client
+                .get(state.config.endpoint(path))
+                .query(&[("page", page), ("per_page", per_page)]);
+            if let Some(token) = &state.token {
+                request = request.bearer_auth(token);
+            }
+
+            let response = request.send().await.map_err(request_error)?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(crate::Error::RequestError(request_id, format!("{status}: {body}")));
+            }
+
+            let page_items = response
+                .json::<Vec<serde_json::Value>>()
+                .await
+                .map_err(request_error)?;
+            let is_last_page = page_items.len() < per_page;
+            items.extend(page_items);
+
+            if is_last_page {
+                return Ok(items);
+            }
+        }
+
+        tracing::warn!("[{}] Stopped fetching {} after {} pages", request_id, path, MAX_FETCH_PAGES);
+        Ok(items)
+    }
+
+    async fn system_ping(state: &WebState, base_url: &str, request_id: uuid::Uuid) -> PingResult {
+        let url = format!("{}/api/{}/system/ping", base_url, state.config.api_version);
+        let response = match state.client.get(url).send().await {
+            Ok(response) => response,
+            Err(err) => {
+                tracing::warn!("[{}] Ping failed: {}", request_id, err);
+                return PingResult::default();
+            }
+        };
+
+        let success = response.status().is_success();
+        let status = response
+            .json::<serde_json::Value>()
+            .await
+            .ok()
+            .and_then(|body| body.get("status")?.as_str().map(str::to_string));
+
+        // Any web server can answer 200, only Mattermost reports its status as OK
+        PingResult {
+            reachable: success && status.as_deref() == Some("OK"),
+            status,
+        }
+    }
+
+    async fn login(
+        state: &WebState,
+        login_data: &LoginData,
+        request_id: uuid::Uuid,
+    ) -> Result<LoginResponse, crate::Error> {
+        let request_error = |err: reqwest::Error| crate::Error::RequestError(request_id, err.to_string());
+
+        let response = state
+            .client
+            .post(state.config.endpoint("users/login"))
+            .json(login_data)
+            .send()
+            .await
+            .map_err(request_error)?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let app_error = serde_json::from_str::<AppError>(&body).unwrap_or_default();
+            if let Some(errors) = app_error.to_field_errors() {
+                return Err(crate::Error::Validation(request_id, errors));
+            }
+            return Err(crate::Error::RequestError(request_id, format!("{status}: {body}")));
+        }
+
+        let token = response
+            .headers()
+            .get("Token")
+            .and_then(|token| token.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let user = response.json::<User>().await.map_err(request_error)?;
+
+        Ok(LoginResponse { user, token, request_id })
+    }
+
+    async fn mock_login_response(login_data: &LoginData, request_id: uuid::Uuid) -> LoginResponse {
         tokio::time::sleep(std::time::Duration::from_secs(1)).await; // Simulate network delay
         let mock_user = User {
             id: "mock_user_id_12345".to_string(),
@@ -61,7 +309,107 @@ impl WebApi {
         LoginResponse {
             user: mock_user,
             token: "mock_session_token_abcdef123456789".to_string(),
+            request_id,
+        }
+    }
+}
+
+/// Cleans up a user supplied server URL so later path joins are predictable.
+/// Trailing slashes are dropped and a missing scheme defaults to `https://`
+/// (`http://` for loopback hosts). Explicit `http://` is always kept.
+/// IPv6 hosts must be bracketed, e.g. `http://[::1]:8065`.
+pub fn normalize_base_url(base_url: &str) -> Result<String, crate::Error> {
+    let base_url = base_url.trim().trim_end_matches('/');
+    let invalid = || crate::Error::InvalidParamError(format!("Invalid server URL: {base_url:?}"));
+
+    let (scheme, rest) = match base_url.split_once("://") {
+        Some((scheme, rest)) => (Some(scheme.to_ascii_lowercase()), rest),
+        None => (None, base_url),
+    };
+    let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(bracketed) => {
+            let (host, port) = bracketed.split_once(']').ok_or_else(invalid)?;
+            host.parse::<std::net::Ipv6Addr>().map_err(|_| invalid())?;
+            match port {
+                "" => (host, None),
+                port => (host, Some(port.strip_prefix(':').ok_or_else(invalid)?)),
+            }
+        }
+        None => {
+            let (host, port) = match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            };
+            let valid_host = !host.is_empty()
+                && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+                && !host.starts_with(['.', '-'])
+                && !host.ends_with(['.', '-']);
+            if !valid_host {
+                return Err(invalid());
+            }
+            (host, port)
         }
+    };
+    if let Some(port) = port {
+        port.parse::<u16>().map_err(|_| invalid())?;
+    }
+
+    let scheme = match scheme.as_deref() {
+        Some("http") => "http",
+        Some("https") => "https",
+        Some(_) => return Err(invalid()),
+        None if matches!(host, "localhost" | "127.0.0.1" | "::1") => "http",
+        None => "https",
+    };
+
+    if path.is_empty() {
+        Ok(format!("{scheme}://{authority}"))
+    } else {
+        Ok(format!("{scheme}://{authority}/{path}"))
+    }
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_else(|err| {
+            tracing::error!("Failed to build HTTP client, falling back to defaults: {}", err);
+            reqwest::Client::new()
+        })
+}
+
+struct WebState {
+    mode: ServiceMode,
+    client: reqwest::Client,
+    token: Option<String>,
+    user_images: LruCache<(String, i64), UserImage>,
+    config: WebConfig,
+    connection_state: crate::ConnectionState,
+    navigation: NavigationApi,
+}
+
+impl WebState {
+    async fn set_connection_state(&mut self, connection_state: crate::ConnectionState) {
+        if self.connection_state == connection_state {
+            return;
+        }
+        self.connection_state = connection_state;
+        self.navigation
+            .set_connection_state_async(connection_state)
+            .await
+            .unwrap_or_else(|err| tracing::error!("Failed to update connection state: {:?}", err));
+    }
+
+    /// A request that got an answer means the server is up; a failed one means it isn't.
+    async fn track_result<T>(&mut self, result: &Result<T, crate::Error>) {
+        self.set_connection_state(match result {
+            Ok(_) => crate::ConnectionState::Connected,
+            Err(_) => crate::ConnectionState::Disconnected,
+        })
+        .await;
     }
 }
 
@@ -71,6 +419,12 @@ struct WebConfig {
     api_version: String,
 }
 
+impl WebConfig {
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/api/{}/{}", self.base_url, self.api_version, path)
+    }
+}
+
 impl Default for WebConfig {
     fn default() -> Self {
         Self {
@@ -78,4 +432,312 @@ impl Default for WebConfig {
             api_version: "v4".to_string(),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::NavigationApiCommand;
+    use std::io::{BufRead, BufReader, Write};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::oneshot;
+
+    /// Serves HTTP on a local port, answering each request line with `respond`'s status and JSON body.
+    fn serve(respond: impl Fn(&str) -> (u16, String) + Send + 'static) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+
+                let (status, body) = respond(request_line.trim_end());
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+        base_url
+    }
+
+    async fn ping(web: &WebApi, base_url: &str) -> PingResult {
+        let (tx, rx) = oneshot::channel();
+        web.ping(base_url, move |result| tx.send(result).unwrap()).unwrap();
+        rx.await.unwrap().unwrap()
+    }
+
+    fn start_web(mode: ServiceMode, tasks: &TaskTracker) -> WebApi {
+        let web = WebApi::new();
+        web.clone().start_service(NavigationApi::new(), tasks).unwrap();
+        web.set_mode(mode).unwrap();
+        web
+    }
+
+    fn login_data() -> LoginData {
+        LoginData {
+            login_id: "jane@example.com".to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn concurrent_logins_get_distinct_request_ids() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Mock, &tasks);
+
+        let (first_tx, first_rx) = oneshot::channel();
+        let (second_tx, second_rx) = oneshot::channel();
+        web.user_login(login_data(), move |result| first_tx.send(result).unwrap()).unwrap();
+        web.user_login(login_data(), move |result| second_tx.send(result).unwrap()).unwrap();
+
+        let first = first_rx.await.unwrap().unwrap();
+        let second = second_rx.await.unwrap().unwrap();
+        assert_ne!(first.request_id, second.request_id);
+        assert!(!first.request_id.is_nil());
+    }
+
+    #[test]
+    fn normalize_defaults_scheme() {
+        assert_eq!(normalize_base_url("chat.example.com").unwrap(), "https://chat.example.com");
+        assert_eq!(normalize_base_url("localhost:8065").unwrap(), "http://localhost:8065");
+        assert_eq!(normalize_base_url("http://chat.example.com").unwrap(), "http://chat.example.com");
+        assert_eq!(normalize_base_url("HTTPS://chat.example.com").unwrap(), "https://chat.example.com");
+    }
+
+    #[test]
+    fn normalize_trims_trailing_slashes() {
+        assert_eq!(normalize_base_url(" https://chat.example.com// ").unwrap(), "https://chat.example.com");
+        assert_eq!(
+            normalize_base_url("https://example.com/mattermost/").unwrap(),
+            "https://example.com/mattermost"
+        );
+    }
+
+    #[test]
+    fn normalize_accepts_bracketed_ipv6() {
+        assert_eq!(normalize_base_url("[::1]:8065").unwrap(), "http://[::1]:8065");
+        assert_eq!(normalize_base_url("https://[2001:db8::1]").unwrap(), "https://[2001:db8::1]");
+    }
+
+    #[test]
+    fn normalize_rejects_garbage() {
+        let garbage = [
+            "",
+            "not a url",
+            "ftp://chat.example.com",
+            "https://",
+            "chat.example.com:port",
+            "-chat.example.com",
+            "https://[::1",
+            "https://[nope]:8065",
+            "https://[::1]8065",
+        ];
+        for url in garbage {
+            assert!(normalize_base_url(url).is_err(), "{url:?} should be rejected");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mock_mode_returns_canned_login() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Live, &tasks);
+
+        // Point at a server that doesn't exist, then switch to mock mode
+        web.set_config("http://127.0.0.1:1", "v4", |result| result.unwrap()).unwrap();
+        web.set_mode(ServiceMode::Mock).unwrap();
+
+        let (tx, rx) = oneshot::channel();
+        web.user_login(login_data(), move |result| tx.send(result).unwrap()).unwrap();
+        let response = rx.await.unwrap().unwrap();
+
+        assert_eq!(response.user.username, "jane");
+        assert_eq!(response.user.email, "jane@example.com");
+        assert_eq!(response.token, "mock_session_token_abcdef123456789");
+        assert!(!format!("{response:?}").contains(&response.token));
+    }
+
+    #[tokio::test]
+    async fn ping_reaches_mattermost_server() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Live, &tasks);
+        let base_url = serve(|request| {
+            assert!(request.starts_with("GET /api/v4/system/ping "), "{request}");
+            (200, r#"{"status":"OK"}"#.to_string())
+        });
+
+        let result = ping(&web, &base_url).await;
+        assert!(result.reachable);
+        assert_eq!(result.status.as_deref(), Some("OK"));
+    }
+
+    #[tokio::test]
+    async fn ping_rejects_other_servers() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Live, &tasks);
+        let base_url = serve(|_| (200, "<html></html>".to_string()));
+
+        let result = ping(&web, &base_url).await;
+        assert!(!result.reachable);
+        assert_eq!(result.status, None);
+    }
+
+    #[tokio::test]
+    async fn ping_reports_unreachable_server() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Live, &tasks);
+
+        let result = ping(&web, "http://127.0.0.1:1").await;
+        assert!(!result.reachable);
+        assert_eq!(result.status, None);
+    }
+
+    #[tokio::test]
+    async fn fetch_all_collects_pages_until_short_page() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Live, &tasks);
+        let base_url = serve(|request| {
+            let page = match request {
+                r if r.starts_with("GET /api/v4/teams/team/channels?page=0&per_page=2 ") => 0,
+                r if r.starts_with("GET /api/v4/teams/team/channels?page=1&per_page=2 ") => 1,
+                r if r.starts_with("GET /api/v4/teams/team/channels?page=2&per_page=2 ") => 2,
+                r => panic!("unexpected request {r}"),
+            };
+            let ids: &[&str] = match page {
+                0 => &["a", "b"],
+                1 => &["c", "d"],
+                _ => &["e"],
+            };
+            let items: Vec<_> = ids.iter().map(|id| serde_json::json!({ "id": id })).collect();
+            (200, serde_json::to_string(&items).unwrap())
+        });
+
+        web.set_config(&base_url, "v4", |result| result.unwrap()).unwrap();
+        let (tx, rx) = oneshot::channel();
+        web.fetch_all::<Channel>("teams/team/channels", 2, move |result| tx.send(result).unwrap())
+            .unwrap();
+
+        let channels = rx.await.unwrap().unwrap();
+        let ids: Vec<_> = channels.iter().map(|channel| channel.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c", "d", "e"]);
+    }
+
+    #[tokio::test]
+    async fn unchanged_user_image_is_served_from_cache() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Live, &tasks);
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let base_url = serve(move |request| {
+            assert!(request.starts_with("GET /api/v4/users/user/image "), "{request}");
+            counter.fetch_add(1, Ordering::SeqCst);
+            (200, "png".to_string())
+        });
+        web.set_config(&base_url, "v4", |result| result.unwrap()).unwrap();
+
+        let fetch = |last_picture_update| {
+            let (tx, rx) = oneshot::channel();
+            web.get_user_image("user", last_picture_update, move |result| tx.send(result).unwrap())
+                .unwrap();
+            async move { rx.await.unwrap().unwrap() }
+        };
+
+        assert!(matches!(fetch(1).await, UserImage::Image(bytes) if &*bytes == b"png"));
+        assert!(matches!(fetch(1).await, UserImage::Image(bytes) if &*bytes == b"png"));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // A newer picture is fetched again, and so is everything after switching servers
+        fetch(2).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        web.set_config(&base_url, "v4", |result| result.unwrap()).unwrap();
+        fetch(2).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn mock_user_images_are_not_cached() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Mock, &tasks);
+        let (tx, rx) = oneshot::channel();
+        web.get_user_image("user", 1, move |result| tx.send(result).unwrap()).unwrap();
+        assert!(matches!(rx.await.unwrap(), Ok(UserImage::Default)));
+
+        // Live against an unreachable server: a leftover mock entry would hide the failure
+        web.set_mode(ServiceMode::Live).unwrap();
+        web.set_config("http://127.0.0.1:1", "v4", |result| result.unwrap()).unwrap();
+        let (tx, rx) = oneshot::channel();
+        web.get_user_image("user", 1, move |result| tx.send(result).unwrap()).unwrap();
+        assert!(matches!(rx.await.unwrap(), Err(crate::Error::RequestError(..))));
+    }
+
+    #[tokio::test]
+    async fn rejected_config_carries_request_id() {
+        let tasks = TaskTracker::new();
+        let web = start_web(ServiceMode::Live, &tasks);
+
+        let (tx, rx) = oneshot::channel();
+        web.set_config("not a url", "v4", move |result| tx.send(result).unwrap()).unwrap();
+        match rx.await.unwrap() {
+            Err(crate::Error::RequestError(request_id, message)) => {
+                assert!(!request_id.is_nil());
+                assert!(message.contains("Invalid server URL"), "{message}");
+            }
+            result => panic!("expected a request error, got {result:?}"),
+        }
+    }
+
+    async fn next_connection_state(navigation: &NavigationApi) -> crate::ConnectionState {
+        match navigation.commands.recv().await.unwrap() {
+            NavigationApiCommand::SetConnectionState(connection_state) => connection_state,
+            _ => panic!("expected a connection state update"),
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_login_goes_from_connecting_to_disconnected() {
+        let tasks = TaskTracker::new();
+        let navigation = NavigationApi::new();
+        let web = WebApi::new();
+        web.clone().start_service(navigation.clone(), &tasks).unwrap();
+
+        // Nothing listens on port 1, so the login request fails to connect
+        let (config_tx, config_rx) = oneshot::channel();
+        web.set_config("http://127.0.0.1:1", "v4", move |result| config_tx.send(result).unwrap())
+            .unwrap();
+        config_rx.await.unwrap().unwrap();
+        assert_eq!(next_connection_state(&navigation).await, crate::ConnectionState::Connecting);
+
+        let (login_tx, login_rx) = oneshot::channel();
+        web.user_login(login_data(), move |result| login_tx.send(result).unwrap()).unwrap();
+        assert!(matches!(login_rx.await.unwrap(), Err(crate::Error::RequestError(..))));
+        assert_eq!(next_connection_state(&navigation).await, crate::ConnectionState::Disconnected);
+
+        // Later requests keep the indicator in sync: a good page list reconnects, a failed picture drops it
+        let base_url = serve(|request| match request {
+            r if r.starts_with("GET /api/v4/teams/team/channels?") => (200, "[]".to_string()),
+            _ => (500, r#"{"id":"app.internal"}"#.to_string()),
+        });
+        let (config_tx, config_rx) = oneshot::channel();
+        web.set_config(&base_url, "v4", move |result| config_tx.send(result).unwrap()).unwrap();
+        config_rx.await.unwrap().unwrap();
+        assert_eq!(next_connection_state(&navigation).await, crate::ConnectionState::Connecting);
+
+        let (fetch_tx, fetch_rx) = oneshot::channel();
+        web.get_team_channels("team", move |result| fetch_tx.send(result).unwrap()).unwrap();
+        assert!(fetch_rx.await.unwrap().unwrap().is_empty());
+        assert_eq!(next_connection_state(&navigation).await, crate::ConnectionState::Connected);
+
+        let (image_tx, image_rx) = oneshot::channel();
+        web.get_user_image("user", 1, move |result| image_tx.send(result).unwrap()).unwrap();
+        assert!(matches!(image_rx.await.unwrap(), Err(crate::Error::RequestError(..))));
+        assert_eq!(next_connection_state(&navigation).await, crate::ConnectionState::Disconnected);
+    }
+}
@@ -44,6 +44,7 @@ pub struct Timezone {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
 pub struct User {
     pub id: String,
     pub create_at: i64,
@@ -80,13 +81,132 @@ pub struct User {
     pub terms_of_service_create_at: Option<i64>,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Default)]
 pub struct LoginResponse {
     pub user: User,
     pub token: String,
+    /// Correlation id of the request that produced this response.
+    pub request_id: uuid::Uuid,
+}
+
+// The session token grants full account access, keep it out of logs
+impl std::fmt::Debug for LoginResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoginResponse")
+            .field("user", &self.user)
+            .field("token", &"<redacted>")
+            .field("request_id", &self.request_id)
+            .finish()
+    }
+}
+
+/// https://developers.mattermost.com/api-documentation/#/operations/GetPublicChannelsForTeam
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Channel {
+    pub id: String,
+    pub create_at: i64,
+    pub update_at: i64,
+    pub delete_at: i64,
+    pub team_id: String,
+    #[serde(rename = "type")]
+    pub channel_type: String,
+    pub display_name: String,
+    pub name: String,
+    pub header: String,
+    pub purpose: String,
+    pub last_post_at: i64,
+    pub total_msg_count: i64,
+    pub creator_id: String,
+}
+
+/// Profile picture returned by `GetUserImage`. `Default` means the server has
+/// no picture for the user and the UI should draw its own placeholder.
+#[derive(Clone, Debug)]
+pub enum UserImage {
+    Image(std::sync::Arc<[u8]>),
+    Default,
+}
+
+/// Error body returned by the Mattermost API on failed requests.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct AppError {
+    pub id: String,
+    pub message: String,
+    pub detailed_error: String,
+    pub request_id: String,
+    pub status_code: i32,
+}
+
+impl AppError {
+    /// Maps error ids that concern a specific login field to form errors.
+    pub fn to_field_errors(&self) -> Option<Vec<crate::FieldError>> {
+        let field = match self.id.as_str() {
+            id if id.starts_with("api.user.login.invalid_credentials") => crate::FIELD_PASSWORD,
+            "api.user.login.blank_pwd.app_error" => crate::FIELD_PASSWORD,
+            "api.user.check_user_login_attempts.too_many.app_error" => crate::FIELD_PASSWORD,
+            "api.user.login.inactive.app_error" => crate::FIELD_USERNAME,
+            "api.user.login.not_verified.app_error" => crate::FIELD_USERNAME,
+            _ => return None,
+        };
+        Some(vec![crate::FieldError::new(field, &self.message)])
+    }
+}
+
+/// https://developers.mattermost.com/api-documentation/#/operations/GetPing
+#[derive(Clone, Debug, Default)]
+pub struct PingResult {
+    pub reachable: bool,
+    pub status: Option<String>,
 }
 
+/// Selects whether commands talk to the configured server or answer with canned data.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ServiceMode {
+    Mock,
+    #[default]
+    Live,
+}
+
+#[derive(strum_macros::IntoStaticStr)]
 pub enum WebApiCommand {
-    SetConfig(String, String, Box<dyn FnOnce() + Send>),
+    SetMode(ServiceMode),
+    SetConfig(String, String, Box<dyn FnOnce(Result<(), crate::Error>) + Send>),
     UserLogin(LoginData, Box<dyn FnOnce(Result<LoginResponse, crate::Error>) + Send>),
+    Ping(String, Box<dyn FnOnce(Result<PingResult, crate::Error>) + Send>),
+    GetUserImage(String, i64, Box<dyn FnOnce(Result<UserImage, crate::Error>) + Send>),
+    FetchAll(String, usize, Box<dyn FnOnce(Result<Vec<serde_json::Value>, crate::Error>) + Send>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_credentials_map_to_password_field() {
+        let body = r#"{
+            "id": "api.user.login.invalid_credentials_email_username",
+            "message": "Enter a valid email or username and/or password.",
+            "detailed_error": "",
+            "request_id": "6tq8wbxd3fgqbpjb1i8xy4nn7r",
+            "status_code": 401
+        }"#;
+        let app_error: AppError = serde_json::from_str(body).unwrap();
+
+        assert_eq!(
+            app_error.to_field_errors(),
+            Some(vec![crate::FieldError::new(
+                crate::FIELD_PASSWORD,
+                "Enter a valid email or username and/or password."
+            )])
+        );
+    }
+
+    #[test]
+    fn unknown_error_ids_are_not_field_errors() {
+        let app_error: AppError =
+            serde_json::from_str(r#"{"id": "app.some.other_error", "status_code": 500}"#).unwrap();
+        assert_eq!(app_error.to_field_errors(), None);
+    }
 }
@@ -2,6 +2,9 @@ use std::sync::Arc;
 
 mod loginpage;
 pub use loginpage::*;
+
+mod navigation;
+pub use navigation::*;
 use slint::Weak;
 
 use crate::services::ServicesApi;
@@ -10,10 +13,12 @@ use crate::services::ServicesApi;
 #[allow(dead_code)]
 pub struct ViewModels {
     pub loginpage: LoginPageManager,
+    pub navigation: NavigationManager,
 }
 
 pub async fn initialize(ui: Weak<crate::Main>, api: ServicesApi) -> Result<Arc<ViewModels>, crate::Error> {
-    let loginpage = LoginPageManager::new(ui, api).await?;
+    let loginpage = LoginPageManager::new(ui.clone(), api.clone()).await?;
+    let navigation = NavigationManager::new(ui, api).await?;
 
-    Ok(Arc::new(ViewModels { loginpage }))
+    Ok(Arc::new(ViewModels { loginpage, navigation }))
 }
@@ -1,6 +1,86 @@
 use slint::{ComponentHandle, Weak};
 
-use crate::services::ServicesApi;
+use crate::{FIELD_GENERAL, FIELD_PASSWORD, FIELD_SERVER_URL, FIELD_USERNAME, FieldError};
+use crate::services::{AuthApi, LoginData, SavedCredentials, ServicesApi, normalize_base_url};
+
+/// Checks the login form before anything is sent to the server.
+pub fn validate(login_data: &LoginData, server_url: &str) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if login_data.login_id.trim().is_empty() {
+        errors.push(FieldError::new(FIELD_USERNAME, "Username is required"));
+    }
+
+    if login_data.password.is_empty() {
+        errors.push(FieldError::new(FIELD_PASSWORD, "Password is required"));
+    }
+
+    let server_url = server_url.trim();
+    if server_url.is_empty() {
+        errors.push(FieldError::new(FIELD_SERVER_URL, "Server URL is required"));
+    } else if normalize_base_url(server_url).is_err() {
+        errors.push(FieldError::new(
+            FIELD_SERVER_URL,
+            "Server URL must look like https://chat.example.com",
+        ));
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+fn to_ui_errors(errors: &[FieldError]) -> crate::LoginPageErrors {
+    let mut ui_errors = crate::LoginPageErrors::default();
+    for error in errors {
+        let message = error.message.as_str().into();
+        match error.field.as_str() {
+            FIELD_USERNAME => ui_errors.username = message,
+            FIELD_PASSWORD => ui_errors.password = message,
+            FIELD_SERVER_URL => ui_errors.server_url = message,
+            FIELD_GENERAL => ui_errors.general = message,
+            _ => log::warn!("Unknown login field error: {:?}", error),
+        }
+    }
+    ui_errors
+}
+
+/// Infers the caps lock state from typed letters, since Slint key events don't
+/// report it: an uppercase letter without shift (or lowercase with shift) means
+/// caps lock is on. Keys that aren't cased letters leave the state unchanged.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CapsLockTracker {
+    caps_lock_on: bool,
+}
+
+impl CapsLockTracker {
+    pub fn observe(&mut self, text: &str, shift: bool) -> bool {
+        let mut chars = text.chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && c.is_alphabetic()
+            && c.is_uppercase() != c.is_lowercase()
+        {
+            self.caps_lock_on = c.is_uppercase() != shift;
+        }
+        self.caps_lock_on
+    }
+}
+
+/// Saves the credentials after a successful login when the user asked to be remembered,
+/// and forgets any previously saved ones otherwise.
+pub fn remember_credentials(
+    auth: &AuthApi,
+    remember_me: bool,
+    credentials: SavedCredentials,
+) -> Result<(), crate::Error> {
+    if remember_me {
+        auth.save_credentials(credentials, |result| {
+            result.unwrap_or_else(|err| log::error!("Failed to save credentials: {:?}", err));
+        })
+    } else {
+        auth.clear_credentials(|result| {
+            result.unwrap_or_else(|err| log::error!("Failed to clear credentials: {:?}", err));
+        })
+    }
+}
 
 pub struct LoginPageManager {}
 
@@ -9,49 +89,262 @@ impl LoginPageManager {
         let main = ui.upgrade().ok_or(crate::Error::UiUpgradeFailed)?;
         let store = main.global::<crate::LoginPageStore>();
 
-        // let auth_service = crate::services::get::<crate::services::AuthApi>();
-        // if auth_service.has_saved_credentials().await {
-        //     store.set_data(aith_service.load_saved_credentials().await?);
-        // }
+        let ui_clone = ui.clone();
+        api.auth.load_credentials(move |result| match result {
+            Ok(Some(credentials)) => {
+                ui_clone.upgrade_in_event_loop(move |main| {
+                    let store = main.global::<crate::LoginPageStore>();
+                    let mut data = store.get_data();
+                    data.server_url = credentials.server_url.into();
+                    data.username = credentials.username.into();
+                    data.remember_me = true;
+                    store.set_data(data);
+                }).ok();
+            }
+            Ok(None) => {}
+            Err(err) => log::error!("Failed to load saved credentials: {:?}", err),
+        })?;
+
+        let caps_lock = std::cell::RefCell::new(CapsLockTracker::default());
+        let ui_clone = ui.clone();
+        store.on_key_typed(move |text, shift| {
+            let caps_lock_on = caps_lock.borrow_mut().observe(&text, shift);
+            if let Some(main) = ui_clone.upgrade() {
+                let store = main.global::<crate::LoginPageStore>();
+                if store.get_caps_lock_on() != caps_lock_on {
+                    store.set_caps_lock_on(caps_lock_on);
+                }
+            }
+        });
 
         store.on_login_clicked(move || {
             if let Some(main) = ui.upgrade() {
                 let store = main.global::<crate::LoginPageStore>();
                 let data = store.get_data();
-                api.navigation.update_loader(true).ok();
+                let login_data = LoginData {
+                    login_id: data.username.to_string(),
+                    password: data.password.to_string(),
+                    ..Default::default()
+                };
+
+                if let Err(errors) = validate(&login_data, &data.server_url) {
+                    store.set_errors(to_ui_errors(&errors));
+                    return;
+                }
+                store.set_errors(Default::default());
+
+                let remember_me = data.remember_me;
+                let credentials = SavedCredentials {
+                    server_url: normalize_base_url(&data.server_url)
+                        .unwrap_or_else(|_| data.server_url.trim().to_string()),
+                    username: data.username.to_string(),
+                    ..Default::default()
+                };
+
+                api.navigation.push_loader().ok();
                 
                 let api_clone = api.clone();
+                let ui = ui.clone();
                 api.web.set_config(
-                    &data.server_url,
+                    data.server_url.trim(),
                     "v4",
-                    move || {
-                        let login_data = crate::services::LoginData {
-                            login_id: data.username.to_string(),
-                            password: data.password.to_string(),
-                            ..Default::default()
-                        };
+                    move |result| {
                         let api = api_clone.clone();
-                        api_clone.clone().web.user_login(login_data, move |result| {
-                            api.navigation.update_loader(false).ok();
+                        if let Err(err) = result {
+                            api.navigation.pop_loader().ok();
+                            log::error!("Invalid server config: {:?}", err);
+                            return;
+                        }
 
+                        let server_url = credentials.server_url.clone();
+                        api_clone.web.ping(&server_url, move |result| {
                             match result {
-                                Ok(response) => {
-                                    log::warn!("Login successful: {:?}", response);
+                                Ok(ping) if ping.reachable => {
+                                    Self::login(ui, api, login_data, remember_me, credentials);
                                 }
-                                Err(err) => {
-                                    log::error!("Login failed: {:?}", err);
+                                result => {
+                                    api.navigation.pop_loader().ok();
+                                    log::error!("Server is not reachable: {:?}", result);
+                                    ui.upgrade_in_event_loop(|main| {
+                                        let store = main.global::<crate::LoginPageStore>();
+                                        store.set_errors(to_ui_errors(&[FieldError::new(
+                                            FIELD_SERVER_URL,
+                                            "Can't reach a Mattermost server at this URL",
+                                        )]));
+                                    }).ok();
                                 }
                             }
-                        }).unwrap_or_else(|err| log::error!("Failed to send login request: {:?}", err));
+                        }).unwrap_or_else(|err| {
+                            api_clone.navigation.pop_loader().ok();
+                            log::error!("Failed to send ping request: {:?}", err);
+                        });
                     },
-                ).unwrap_or_else(|err| log::error!("Failed to set config: {:?}", err));
+                ).unwrap_or_else(|err| {
+                    api.navigation.pop_loader().ok();
+                    log::error!("Failed to set config: {:?}", err);
+                });
             }
         });
 
-        // event.subscribe(Event.LoggedIn, move |_| {
-        //     navigation_service.navigate_to(crate::NavigationTarget::MainPage);
-        // });
-
         Ok(Self {})
     }
+
+    fn login(
+        ui: Weak<crate::Main>,
+        api: ServicesApi,
+        login_data: LoginData,
+        remember_me: bool,
+        credentials: SavedCredentials,
+    ) {
+        let api_clone = api.clone();
+        api.web.user_login(login_data, move |result| {
+            let api = api_clone;
+            api.navigation.pop_loader().ok();
+
+            match result {
+                Ok(response) => {
+                    log::info!("[{}] Logged in as {}", response.request_id, response.user.username);
+                    api.navigation.navigate_to(crate::NavigationTarget::Main).ok();
+
+                    remember_credentials(&api.auth, remember_me, credentials)
+                        .unwrap_or_else(|err| log::error!("Failed to update saved credentials: {:?}", err));
+                }
+                Err(crate::Error::Validation(request_id, errors)) => {
+                    log::warn!("[{}] Login rejected: {:?}", request_id, errors);
+                    ui.upgrade_in_event_loop(move |main| {
+                        let store = main.global::<crate::LoginPageStore>();
+                        store.set_errors(to_ui_errors(&errors));
+                    }).ok();
+                }
+                Err(err) => {
+                    log::error!("Login failed: {:?}", err);
+                    let message = match &err {
+                        crate::Error::RequestError(request_id, _) => {
+                            format!("Login failed, please try again (ref {request_id})")
+                        }
+                        _ => "Login failed, please try again".to_string(),
+                    };
+                    ui.upgrade_in_event_loop(move |main| {
+                        let store = main.global::<crate::LoginPageStore>();
+                        store.set_errors(to_ui_errors(&[FieldError::new(FIELD_GENERAL, &message)]));
+                    }).ok();
+                }
+            }
+        }).unwrap_or_else(|err| {
+            api.navigation.pop_loader().ok();
+            log::error!("Failed to send login request: {:?}", err);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login_data(username: &str, password: &str) -> LoginData {
+        LoginData {
+            login_id: username.to_string(),
+            password: password.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn failed_fields(result: Result<(), Vec<FieldError>>) -> Vec<String> {
+        result.unwrap_err().into_iter().map(|error| error.field).collect()
+    }
+
+    #[test]
+    fn accepts_a_complete_form() {
+        assert!(validate(&login_data("jane", "secret"), "https://chat.example.com").is_ok());
+    }
+
+    #[test]
+    fn requires_username() {
+        let result = validate(&login_data("  ", "secret"), "https://chat.example.com");
+        assert_eq!(failed_fields(result), [FIELD_USERNAME]);
+    }
+
+    #[test]
+    fn requires_password() {
+        let result = validate(&login_data("jane", ""), "https://chat.example.com");
+        assert_eq!(failed_fields(result), [FIELD_PASSWORD]);
+    }
+
+    #[test]
+    fn requires_server_url() {
+        let result = validate(&login_data("jane", "secret"), " ");
+        assert_eq!(failed_fields(result), [FIELD_SERVER_URL]);
+    }
+
+    #[test]
+    fn rejects_malformed_server_url() {
+        let result = validate(&login_data("jane", "secret"), "not a url");
+        assert_eq!(failed_fields(result), [FIELD_SERVER_URL]);
+    }
+
+    #[test]
+    fn reports_every_failing_field() {
+        let result = validate(&login_data("", ""), "");
+        assert_eq!(failed_fields(result), [FIELD_USERNAME, FIELD_PASSWORD, FIELD_SERVER_URL]);
+    }
+
+    #[test]
+    fn general_errors_leave_fields_untouched() {
+        let errors = to_ui_errors(&[FieldError::new(FIELD_GENERAL, "Login failed, please try again")]);
+        assert_eq!(errors.general, "Login failed, please try again");
+        assert_eq!(errors.server_url, "");
+        assert_eq!(errors.password, "");
+        assert_eq!(errors.username, "");
+    }
+
+    #[test]
+    fn caps_lock_follows_letter_case_and_shift() {
+        let mut tracker = CapsLockTracker::default();
+
+        assert!(!tracker.observe("a", false));
+        assert!(tracker.observe("A", false));
+        assert!(tracker.observe("a", true));
+        assert!(!tracker.observe("A", true));
+    }
+
+    #[test]
+    fn caps_lock_ignores_keys_without_case() {
+        let mut tracker = CapsLockTracker::default();
+        tracker.observe("Q", false);
+
+        for text in ["1", " ", "\t", "", "ab"] {
+            assert!(tracker.observe(text, false), "{text:?} should keep caps lock on");
+        }
+        assert!(!tracker.observe("q", false));
+    }
+
+    async fn load_saved(auth: &AuthApi) -> Option<SavedCredentials> {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        auth.load_credentials(move |result| tx.send(result).unwrap()).unwrap();
+        rx.await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn remember_me_off_clears_saved_credentials() {
+        let path = std::env::temp_dir()
+            .join(format!("mattermost-slint-{}", uuid::Uuid::new_v4()))
+            .join("credentials.json");
+        let tasks = tokio_util::task::TaskTracker::new();
+        let auth = AuthApi::new();
+        auth.clone().start_service_at(Some(path.clone()), &tasks).unwrap();
+
+        let credentials = SavedCredentials {
+            server_url: "https://chat.example.com".to_string(),
+            username: "jane".to_string(),
+            ..Default::default()
+        };
+        remember_credentials(&auth, true, credentials).unwrap();
+        assert_eq!(load_saved(&auth).await.unwrap().username, "jane");
+
+        remember_credentials(&auth, false, SavedCredentials::default()).unwrap();
+        assert!(load_saved(&auth).await.is_none());
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
 }
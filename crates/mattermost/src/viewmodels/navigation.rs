@@ -0,0 +1,21 @@
+use slint::{ComponentHandle, Weak};
+
+use crate::services::ServicesApi;
+
+/// Forwards page changes requested from Slint to the navigation service.
+pub struct NavigationManager {}
+
+impl NavigationManager {
+    pub async fn new(ui: Weak<crate::Main>, api: ServicesApi) -> Result<Self, crate::Error> {
+        let main = ui.upgrade().ok_or(crate::Error::UiUpgradeFailed)?;
+        let store = main.global::<crate::NavStore>();
+
+        store.on_navigate(move |target| {
+            api.navigation
+                .navigate_to(target)
+                .unwrap_or_else(|err| log::error!("Failed to navigate to {:?}: {:?}", target, err));
+        });
+
+        Ok(Self {})
+    }
+}